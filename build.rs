@@ -1,7 +1,11 @@
 extern crate bindgen;
 
+use bindgen::callbacks::{ItemInfo, ItemKind, ParseCallbacks};
+use std::cell::RefCell;
 use std::env;
+use std::fs;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 #[cfg(all(target_os = "windows", target_arch = "x86"))]
 mod config {
@@ -132,6 +136,46 @@ fn set_ld_library_path(lib_dir: PathBuf) {
     );
 }
 
+/// Collects the names of all functions parsed by bindgen.
+#[derive(Debug, Default)]
+struct ParsedFunctions(Rc<RefCell<Vec<String>>>);
+
+impl ParseCallbacks for ParsedFunctions {
+    fn generated_name_override(&self, item_info: ItemInfo<'_>) -> Option<String> {
+        if let ItemKind::Function = item_info.kind {
+            self.0.borrow_mut().push(item_info.name.to_string());
+        }
+        None
+    }
+}
+
+/// Filter the parsed functions to the ones included in the generated code.
+///
+/// bindgen reports all parsed functions, including the ones not matching the
+/// allowlist. Thus, check for their declaration in the generated code: an
+/// extern function or a field of the dynamic library struct.
+///
+/// Panics, if none of the parsed functions could be found, as this means the
+/// shape of the generated code has changed.
+fn generated_functions(parsed: &[String], code: &str, dynamic_loading: bool) -> Vec<String> {
+    // Ignore the formatting of the generated code
+    let code: String = code.split_whitespace().collect();
+    let mut functions: Vec<String> = parsed
+        .iter()
+        .filter(|name| match dynamic_loading {
+            true => code.contains(&format!("pub{name}:")),
+            false => code.contains(&format!("pubfn{name}(")),
+        })
+        .cloned()
+        .collect();
+    if !parsed.is_empty() && functions.is_empty() {
+        panic!("Unable to find any parsed function in the generated bindings");
+    }
+    functions.sort();
+    functions.dedup();
+    functions
+}
+
 fn main() {
     // Get the path to the SAP NWRFC SDK
     let sdk = PathBuf::from(env::var("SAPNWRFC_HOME").expect(
@@ -148,9 +192,13 @@ fn main() {
     #[cfg(target_os = "linux")]
     set_ld_library_path(lib_dir);
 
-    // Tell cargo to link against the sapnwrfc libs
-    for lib in config::LIBS {
-        println!("cargo:rustc-link-lib={lib}");
+    // Tell cargo to link against the sapnwrfc libs, unless they are loaded
+    // at runtime
+    let dynamic_loading = env::var_os("CARGO_FEATURE_DYNAMIC_LOADING").is_some();
+    if !dynamic_loading {
+        for lib in config::LIBS {
+            println!("cargo:rustc-link-lib={lib}");
+        }
     }
 
     // Set additional link args
@@ -158,16 +206,29 @@ fn main() {
         println!("cargo:rustc-link-arg={}", link_arg);
     }
 
+    // Collect the parsed functions to export them to dependent crates
+    let parsed_functions = ParsedFunctions::default();
+    let parsed = Rc::clone(&parsed_functions.0);
+
     // Add the bindgen wrapper
-    let bindings = bindgen::Builder::default()
+    let include_dir = sdk.join("include");
+    let mut bindings = bindgen::Builder::default()
         // Add custom build arguments for the clang compiler
         .clang_args(config::DEFINES)
         // Build bindings for the sapnwrfc.h header
-        .header(sdk.join("include").join("sapnwrfc.h").to_string_lossy())
-        .header(sdk.join("include").join("sapdecf.h").to_string_lossy())
+        .header(include_dir.join("sapnwrfc.h").to_string_lossy())
+        .header(include_dir.join("sapdecf.h").to_string_lossy())
+        // Only generate the items declared by the SDK headers instead of
+        // everything included by them (e.g. the C standard library). Types
+        // used by these items are pulled in automatically.
+        .allowlist_file(format!(
+            r"{}[/\\].*",
+            regex::escape(&include_dir.to_string_lossy())
+        ))
         // Tell cargo to invalidate the build results if any of the included
         // headers changes
         .parse_callbacks(Box::new(bindgen::CargoCallbacks))
+        .parse_callbacks(Box::new(parsed_functions))
         // Derive some standard traits
         .derive_default(true)
         .derive_copy(true)
@@ -176,18 +237,40 @@ fn main() {
         .derive_ord(true)
         // Use rust enums as default.
         // But make them exhaustive, as we need to re-build the wrappers for
-        // every NW RFC lib version anyway.
+        // every NW RFC lib version anyway. This does not hold when loading
+        // the library at runtime, see below.
         .default_enum_style(bindgen::EnumVariation::Rust {
             non_exhaustive: false,
         })
         // Don't include the documentation as comments
         .generate_comments(true);
 
+    // When loading the library at runtime, wrap all functions into a struct
+    // backed by `libloading`. Missing symbols are only reported when the
+    // function is called, so older library versions can still be loaded.
+    // The loaded library may also be newer than the headers and return enum
+    // values unknown at build time. Rust enums can't hold those, thus use
+    // newtypes with associated constants instead.
+    if dynamic_loading {
+        bindings = bindings
+            .dynamic_library_name("SapNwRfc")
+            .dynamic_link_require_all(false)
+            .default_enum_style(bindgen::EnumVariation::NewType {
+                is_bitfield: false,
+                is_global: false,
+            });
+    }
+
     // generate the bindings
     let out_path = PathBuf::from("src");
-    bindings
+    let code = bindings
         .generate()
         .expect("Unable to generate library bindings")
-        .write_to_file(out_path.join("bindings.rs"))
-        .expect("Unable to write library bindings");
+        .to_string();
+    fs::write(out_path.join("bindings.rs"), &code).expect("Unable to write library bindings");
+
+    // Export the functions available in this SDK version to dependent
+    // crates as `DEP_SAPNWRFC_FUNCTIONS`, so they can gate their usage.
+    let functions = generated_functions(&parsed.borrow(), &code, dynamic_loading);
+    println!("cargo:functions={}", functions.join(","));
 }
@@ -3,7 +3,43 @@
 #[allow(non_camel_case_types)]
 #[allow(non_upper_case_globals)]
 #[allow(improper_ctypes)]
+#[allow(improper_ctypes_definitions)]
+#[allow(clippy::missing_safety_doc)]
+#[allow(clippy::too_many_arguments)]
 mod bindings;
 
 // Re-export everything from the bindings for direct unsafe usage
 pub use bindings::*;
+
+/// Get the version of the SAP NW RFC library as `(major, minor, patch level)`.
+#[cfg(not(feature = "dynamic-loading"))]
+pub fn sdk_version() -> (u32, u32, u32) {
+    let (mut major, mut minor, mut patch_level) = (0, 0, 0);
+    unsafe { RfcGetVersion(&mut major, &mut minor, &mut patch_level) };
+    (major, minor, patch_level)
+}
+
+// Re-export libloading, as its error type is part of the public API
+#[cfg(feature = "dynamic-loading")]
+pub use libloading;
+
+#[cfg(feature = "dynamic-loading")]
+impl SapNwRfc {
+    /// Load the SAP NW RFC library from the default library search path.
+    ///
+    /// Returns an error, if the library could not be found or loaded.
+    ///
+    /// # Safety
+    /// Loading the library runs its initialization routines.
+    /// See [`libloading::Library::new`] for details.
+    pub unsafe fn load() -> Result<Self, libloading::Error> {
+        Self::new(libloading::library_filename("sapnwrfc"))
+    }
+
+    /// Get the version of the loaded library as `(major, minor, patch level)`.
+    pub fn sdk_version(&self) -> (u32, u32, u32) {
+        let (mut major, mut minor, mut patch_level) = (0, 0, 0);
+        unsafe { self.RfcGetVersion(&mut major, &mut minor, &mut patch_level) };
+        (major, minor, patch_level)
+    }
+}